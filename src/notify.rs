@@ -0,0 +1,191 @@
+use crate::backend::nc_request::Token;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Gates which `NotificationEvent`s actually reach the desktop, mirrors
+/// `config.data.notifications`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NotificationLevel {
+    Off,
+    MentionsOnly,
+    #[default]
+    All,
+}
+
+/// How long to keep folding messages from the same room into one burst
+/// before flushing a single coalesced notification for it.
+const COALESCE_WINDOW: Duration = Duration::from_secs(3);
+
+/// A newly-arrived message worth considering for a desktop notification, as
+/// reported by `ChatBox::update_messages`.
+pub struct NotificationEvent {
+    pub room_token: Token,
+    pub actor_display_name: String,
+    pub message_preview: String,
+    pub is_mention: bool,
+}
+
+/// Callback `ChatBox` feeds `NotificationEvent`s into; wired up by the app to
+/// a shared [`Notifier`].
+pub type NotifySink = Box<dyn FnMut(NotificationEvent)>;
+
+/// Messages from a single room, accumulated while still inside
+/// `COALESCE_WINDOW`.
+struct PendingBurst {
+    count: usize,
+    last_actor: String,
+    last_preview: String,
+    is_mention: bool,
+    started_at: Instant,
+}
+
+/// Debounces and de-duplicates `NotificationEvent`s into OS desktop
+/// notifications, coalescing a burst of messages from the same room into a
+/// single "N new messages" popup instead of spamming one per message.
+pub struct Notifier {
+    level: NotificationLevel,
+    /// The room currently open in the UI; its events are never notified,
+    /// since the user is already looking at them.
+    focused_room: Option<Token>,
+    pending: HashMap<Token, PendingBurst>,
+}
+
+impl Notifier {
+    pub fn new(level: NotificationLevel) -> Self {
+        Notifier {
+            level,
+            focused_room: None,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Sets the room the UI currently has open, so its events get skipped.
+    /// Pass `None` while the app itself is backgrounded (no room is "open").
+    pub fn set_focused_room(&mut self, room: Option<Token>) {
+        self.focused_room = room;
+    }
+
+    /// Queues `event` for its room, folding it into any burst already being
+    /// collected for that room. The actual OS notification is raised later,
+    /// by `tick`, once the burst's coalescing window elapses.
+    pub fn notify(&mut self, event: NotificationEvent) {
+        if self.focused_room.as_ref() == Some(&event.room_token) {
+            return;
+        }
+        let allowed = match self.level {
+            NotificationLevel::Off => false,
+            NotificationLevel::MentionsOnly => event.is_mention,
+            NotificationLevel::All => true,
+        };
+        if !allowed {
+            return;
+        }
+
+        self.pending
+            .entry(event.room_token.clone())
+            .and_modify(|burst| {
+                burst.count += 1;
+                burst.last_actor.clone_from(&event.actor_display_name);
+                burst.last_preview.clone_from(&event.message_preview);
+                burst.is_mention |= event.is_mention;
+            })
+            .or_insert(PendingBurst {
+                count: 1,
+                last_actor: event.actor_display_name,
+                last_preview: event.message_preview,
+                is_mention: event.is_mention,
+                started_at: Instant::now(),
+            });
+    }
+
+    /// Flushes any pending burst whose coalescing window has elapsed. The
+    /// app event loop should call this once per tick so bursts actually get
+    /// shown instead of waiting forever for one more message.
+    pub fn tick(&mut self) {
+        let expired: Vec<Token> = self
+            .pending
+            .iter()
+            .filter(|(_, burst)| burst.started_at.elapsed() >= COALESCE_WINDOW)
+            .map(|(room, _)| room.clone())
+            .collect();
+        for room in expired {
+            if let Some(burst) = self.pending.remove(&room) {
+                Self::fire(&room, &burst);
+            }
+        }
+    }
+
+    fn fire(room: &Token, burst: &PendingBurst) {
+        let summary = if burst.is_mention {
+            format!("Mentioned in {room}")
+        } else {
+            format!("New messages in {room}")
+        };
+        let body = if burst.count == 1 {
+            format!("{}: {}", burst.last_actor, burst.last_preview)
+        } else {
+            format!(
+                "{} new messages, latest from {}: {}",
+                burst.count, burst.last_actor, burst.last_preview
+            )
+        };
+
+        if let Err(err) = notify_rust::Notification::new().summary(&summary).body(&body).show() {
+            log::warn!("Failed to show desktop notification: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(room: &str, is_mention: bool) -> NotificationEvent {
+        NotificationEvent {
+            room_token: room.to_string(),
+            actor_display_name: "Alice".to_string(),
+            message_preview: "hello".to_string(),
+            is_mention,
+        }
+    }
+
+    #[test]
+    fn off_level_suppresses_everything() {
+        let mut notifier = Notifier::new(NotificationLevel::Off);
+        notifier.notify(event("room1", true));
+        assert!(notifier.pending.is_empty());
+    }
+
+    #[test]
+    fn mentions_only_ignores_plain_messages() {
+        let mut notifier = Notifier::new(NotificationLevel::MentionsOnly);
+        notifier.notify(event("room1", false));
+        assert!(notifier.pending.is_empty());
+
+        notifier.notify(event("room1", true));
+        assert_eq!(notifier.pending.len(), 1);
+    }
+
+    #[test]
+    fn focused_room_is_skipped() {
+        let mut notifier = Notifier::new(NotificationLevel::All);
+        notifier.set_focused_room(Some("room1".to_string()));
+        notifier.notify(event("room1", false));
+        assert!(notifier.pending.is_empty());
+
+        notifier.notify(event("room2", false));
+        assert_eq!(notifier.pending.len(), 1);
+    }
+
+    #[test]
+    fn bursts_from_the_same_room_coalesce() {
+        let mut notifier = Notifier::new(NotificationLevel::All);
+        notifier.notify(event("room1", false));
+        notifier.notify(event("room1", false));
+        notifier.notify(event("room1", true));
+
+        let burst = notifier.pending.get("room1").unwrap();
+        assert_eq!(burst.count, 3);
+        assert!(burst.is_mention);
+    }
+}
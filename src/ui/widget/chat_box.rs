@@ -1,8 +1,10 @@
 use crate::backend::nc_request::Token;
 use crate::backend::{nc_room::NCRoomInterface, nc_talk::NCBackend};
-use crate::config::Config;
+use crate::config::{Config, TimestampStyle};
+use crate::notify::{NotificationEvent, NotifySink};
 use chrono::{DateTime, Local, Utc};
 use colorhash::ColorHash;
+use itertools::Itertools;
 use ratatui::{
     prelude::*,
     widgets::{Block, Cell, HighlightSpacing, Row, Table, TableState},
@@ -12,10 +14,263 @@ use textwrap::Options;
 // this fits my name, so 20 it is :D
 const NAME_WIDTH: u16 = 20;
 const TIME_WIDTH: u16 = 5;
+// left margin for fenced code blocks so they read as indented
+const CODE_BLOCK_INDENT: &str = "  ";
+
+type MessageId = u64;
+// matched message, score, byte indices matched (into the un-wrapped display text, markdown markers stripped)
+type SearchMatch = (MessageId, i64, Vec<usize>);
+
+// greedy Skim-V2 style subsequence matcher
+fn fuzzy_match(query: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    if query.is_empty() {
+        return None;
+    }
+
+    const BONUS_BOUNDARY: i64 = 10;
+    const BONUS_CONSECUTIVE: i64 = 8;
+    const PENALTY_GAP: i64 = 2;
+
+    let query_chars: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    let candidate_chars: Vec<(usize, char)> = candidate.char_indices().collect();
+
+    let mut matched_indices = Vec::with_capacity(query_chars.len());
+    let mut score: i64 = 0;
+    let mut query_pos = 0;
+    let mut last_match_pos: Option<usize> = None;
+    let mut prev_char: Option<char> = None;
+
+    for (pos, (byte_index, ch)) in candidate_chars.iter().enumerate() {
+        if query_pos >= query_chars.len() {
+            break;
+        }
+        if ch.to_lowercase().next() != Some(query_chars[query_pos]) {
+            prev_char = Some(*ch);
+            continue;
+        }
+
+        let at_boundary = prev_char.is_none_or(char::is_whitespace);
+        if at_boundary {
+            score += BONUS_BOUNDARY;
+        }
+        if let Some(last_pos) = last_match_pos {
+            if pos == last_pos + 1 {
+                score += BONUS_CONSECUTIVE;
+            } else {
+                score -= PENALTY_GAP * (pos - last_pos - 1) as i64;
+            }
+        }
+
+        matched_indices.push(*byte_index);
+        last_match_pos = Some(pos);
+        query_pos += 1;
+        prev_char = Some(*ch);
+    }
+
+    if query_pos == query_chars.len() {
+        Some((score, matched_indices))
+    } else {
+        None
+    }
+}
+
+// locates `trimmed` within `cell`, searching from `search_from` so repeated
+// phrases resolve to the right occurrence instead of always the first
+fn find_wrapped_offset(cell: &str, trimmed: &str, cell_offset: usize, search_from: usize) -> (usize, usize) {
+    let local = cell[search_from..]
+        .find(trimmed)
+        .map_or(search_from, |pos| search_from + pos);
+    (local, cell_offset + local)
+}
+
+// "now" / "5m" / "3h" / "yesterday" / "5d", falling back to a date past a week
+fn relative_time_str(timestamp: i64) -> String {
+    let Some(then) = DateTime::<Utc>::from_timestamp(timestamp, 0) else {
+        return String::new();
+    };
+    let delta = Utc::now().signed_duration_since(then);
+
+    if delta.num_seconds() < 60 {
+        "now".to_string()
+    } else if delta.num_minutes() < 60 {
+        format!("{}m", delta.num_minutes())
+    } else if delta.num_hours() < 24 {
+        format!("{}h", delta.num_hours())
+    } else if delta.num_days() == 1 {
+        "yesterday".to_string()
+    } else if delta.num_days() < 7 {
+        format!("{}d", delta.num_days())
+    } else {
+        DateTime::<Local>::from(then).format("%d/%m").to_string()
+    }
+}
+
+// requires a non-identifier boundary after the match so "@Al" doesn't match inside "@Alice"
+fn is_mentioned(text: &str, user_id: &str, user_name: &str) -> bool {
+    let mentions = |who: &str| {
+        if who.is_empty() {
+            return false;
+        }
+        let needle = format!("@{who}");
+        text.match_indices(&needle).any(|(start, _)| {
+            let after = &text[start + needle.len()..];
+            after.chars().next().is_none_or(|c| !c.is_alphanumeric() && c != '_')
+        })
+    };
+    mentions(user_id) || mentions(user_name)
+}
+
+// base_style everywhere, overridden by markdown_spans, then by search_style
+fn style_line(
+    line: &str,
+    line_start: usize,
+    base_style: Style,
+    markdown_spans: &[(std::ops::Range<usize>, Style)],
+    search_indices: &[usize],
+    search_style: Style,
+) -> Line<'static> {
+    let line_end = line_start + line.len();
+    let local_search: std::collections::HashSet<usize> = search_indices
+        .iter()
+        .copied()
+        .filter(|&i| i >= line_start && i < line_end)
+        .map(|i| i - line_start)
+        .collect();
+
+    if markdown_spans.is_empty() && local_search.is_empty() {
+        return Line::styled(line.to_string(), base_style);
+    }
+
+    let mut spans: Vec<(String, Style)> = Vec::new();
+    let mut idx = 0usize;
+    while idx < line.len() {
+        let ch_len = line[idx..].chars().next().map_or(1, char::len_utf8);
+        let style = if local_search.contains(&idx) {
+            search_style
+        } else if let Some((_, span_style)) = markdown_spans.iter().find(|(r, _)| r.contains(&idx)) {
+            *span_style
+        } else {
+            base_style
+        };
+        match spans.last_mut() {
+            Some((text, last_style)) if *last_style == style => text.push_str(&line[idx..idx + ch_len]),
+            _ => spans.push((line[idx..idx + ch_len].to_string(), style)),
+        }
+        idx += ch_len;
+    }
+    Line::from(
+        spans
+            .into_iter()
+            .map(|(text, style)| Span::styled(text, style))
+            .collect_vec(),
+    )
+}
+
+// markers stripped out, styles gives bold/italic/inline-code byte ranges into text
+struct MdLine {
+    text: String,
+    styles: Vec<(std::ops::Range<usize>, Style)>,
+}
+
+enum MdBlock {
+    Paragraph(Vec<MdLine>),
+    Code(Vec<String>),
+}
+
+// no-op pass-through to one MdLine per line when render_markdown is false
+fn parse_markdown(raw: &str, render_markdown: bool, bold: Style, italic: Style, code: Style) -> Vec<MdBlock> {
+    if !render_markdown {
+        return vec![MdBlock::Paragraph(
+            raw.split('\n')
+                .map(|line| MdLine {
+                    text: line.to_string(),
+                    styles: Vec::new(),
+                })
+                .collect_vec(),
+        )];
+    }
+
+    let mut blocks = Vec::new();
+    for (index, chunk) in raw.split("```").enumerate() {
+        if chunk.is_empty() && raw.len() > 0 {
+            continue;
+        }
+        if index % 2 == 1 {
+            // Inside a fence: drop an optional language tag on the first line,
+            // and the newline right before the closing fence.
+            let chunk = chunk.strip_suffix('\n').unwrap_or(chunk);
+            let lines = chunk.split('\n').collect_vec();
+            let code_lines = if lines.len() > 1 {
+                lines[1..].iter().map(|l| (*l).to_string()).collect_vec()
+            } else {
+                lines.iter().map(|l| (*l).to_string()).collect_vec()
+            };
+            blocks.push(MdBlock::Code(code_lines));
+        } else {
+            // the newline right after a closing fence does not start a line of its own
+            let chunk = if index > 0 {
+                chunk.strip_prefix('\n').unwrap_or(chunk)
+            } else {
+                chunk
+            };
+            let paragraph = chunk
+                .split('\n')
+                .map(|line| parse_inline(line, bold, italic, code))
+                .collect_vec();
+            blocks.push(MdBlock::Paragraph(paragraph));
+        }
+    }
+    blocks
+}
+
+fn parse_inline(line: &str, bold: Style, italic: Style, code: Style) -> MdLine {
+    let bytes = line.as_bytes();
+    let mut text = String::with_capacity(line.len());
+    let mut styles = Vec::new();
+    let mut i = 0usize;
+
+    while i < bytes.len() {
+        if line[i..].starts_with("**") {
+            if let Some(end) = line[i + 2..].find("**") {
+                let start = text.len();
+                let inner = &line[i + 2..i + 2 + end];
+                text.push_str(inner);
+                styles.push((start..text.len(), bold));
+                i += 2 + end + 2;
+                continue;
+            }
+        } else if line[i..].starts_with('`') {
+            if let Some(end) = line[i + 1..].find('`') {
+                let start = text.len();
+                let inner = &line[i + 1..i + 1 + end];
+                text.push_str(inner);
+                styles.push((start..text.len(), code));
+                i += 1 + end + 1;
+                continue;
+            }
+        } else if line[i..].starts_with('*') {
+            if let Some(end) = line[i + 1..].find('*') {
+                let start = text.len();
+                let inner = &line[i + 1..i + 1 + end];
+                text.push_str(inner);
+                styles.push((start..text.len(), italic));
+                i += 1 + end + 1;
+                continue;
+            }
+        }
+        let ch_len = line[i..].chars().next().map_or(1, char::len_utf8);
+        text.push_str(&line[i..i + ch_len]);
+        i += ch_len;
+    }
+
+    MdLine { text, styles }
+}
 
 #[derive(Default)]
 pub struct ChatBox<'a> {
     messages: Vec<Row<'a>>,
+    // message id -> row index, rebuilt on every update_messages
+    message_row_index: std::collections::HashMap<MessageId, usize>,
     current_index: usize,
     width: u16,
     state: TableState,
@@ -25,12 +280,34 @@ pub struct ChatBox<'a> {
     table_header_style: Style,
     date_format: String,
     user_styles: ColorHash,
+    search_query: String,
+    search_matches: Vec<SearchMatch>,
+    search_selected: usize,
+    render_markdown: bool,
+    bold_style: Style,
+    italic_style: Style,
+    code_style: Style,
+    code_block_style: Style,
+    current_user_id: String,
+    current_user_name: String,
+    mention_style: Style,
+    mention_rows: Vec<usize>,
+    row_heights: Vec<u16>,
+    // tracks ids already rendered once, so re-renders don't re-notify
+    seen_message_ids: std::collections::HashSet<MessageId>,
+    // rooms whose pre-existing history has been baselined into seen_message_ids,
+    // so opening a room with unread history doesn't burst-notify for all of it
+    initialized_rooms: std::collections::HashSet<Token>,
+    notify_sink: Option<NotifySink>,
+    timestamp_style: TimestampStyle,
 }
 
-impl ChatBox<'_> {
-    pub fn new(config: &Config) -> Self {
+impl<'a> ChatBox<'a> {
+    pub fn new(config: &Config, current_user_id: &str, current_user_name: &str) -> Self {
+        let default_style = config.theme.default_style();
         ChatBox {
             messages: Vec::new(),
+            message_row_index: std::collections::HashMap::new(),
             current_index: 0,
             width: 10,
             state: TableState::default().with_offset(0).with_selected(0),
@@ -38,32 +315,86 @@ impl ChatBox<'_> {
                 .theme
                 .unread_message_style()
                 .add_modifier(Modifier::BOLD),
-            default_style: config.theme.default_style(),
+            default_style,
             default_highlight_style: config.theme.default_highlight_style(),
             table_header_style: config.theme.table_header_style(),
             date_format: config.data.ui.date_format.clone(),
             user_styles: ColorHash::new().lightness(70.0),
+            search_query: String::new(),
+            search_matches: Vec::new(),
+            search_selected: 0,
+            render_markdown: config.data.ui.render_markdown,
+            bold_style: default_style.add_modifier(Modifier::BOLD),
+            italic_style: default_style.add_modifier(Modifier::ITALIC),
+            code_style: default_style.bg(Color::DarkGray),
+            code_block_style: default_style.bg(Color::Black),
+            current_user_id: current_user_id.to_string(),
+            current_user_name: current_user_name.to_string(),
+            mention_style: config.theme.mention_style(),
+            mention_rows: Vec::new(),
+            row_heights: Vec::new(),
+            seen_message_ids: std::collections::HashSet::new(),
+            initialized_rooms: std::collections::HashSet::new(),
+            notify_sink: None,
+            timestamp_style: config.data.ui.timestamp_style,
+        }
+    }
+
+    // drops the time cell entirely when timestamp_style is Hidden
+    fn row_cells(&self, time: Cell<'a>, name: Cell<'a>, message: Cell<'a>) -> Vec<Cell<'a>> {
+        if self.timestamp_style == TimestampStyle::Hidden {
+            vec![name, message]
+        } else {
+            vec![time, name, message]
         }
     }
 
+    pub fn set_notify_sink(&mut self, sink: NotifySink) {
+        self.notify_sink = Some(sink);
+    }
+
     pub fn set_width_and_update_if_change(
         &mut self,
         width: u16,
         backend: &impl NCBackend,
         current_room: &Token,
     ) {
-        let new_width = (width - TIME_WIDTH - 2 - NAME_WIDTH).max(10);
+        let reserved = NAME_WIDTH
+            + match self.timestamp_style {
+                TimestampStyle::Hidden => 1,
+                TimestampStyle::Absolute | TimestampStyle::Relative => TIME_WIDTH + 2,
+            };
+        let new_width = (width - reserved).max(10);
         if self.width != new_width {
             self.width = new_width;
             self.update_messages(backend, current_room);
         }
     }
 
+    // call periodically so relative timestamps keep aging; no-op otherwise
+    pub fn refresh_timestamps(&mut self, backend: &impl NCBackend, current_room: &Token) {
+        if self.timestamp_style == TimestampStyle::Relative {
+            self.update_messages(backend, current_room);
+        }
+    }
+
+    // keeps row_heights in lock-step with messages for select_line
+    fn push_row(&mut self, row: Row<'a>, height: u16) {
+        self.messages.push(row.height(height));
+        self.row_heights.push(height);
+    }
+
     pub fn update_messages(&mut self, backend: &impl NCBackend, current_room: &Token) {
-        use itertools::Itertools;
         use std::convert::TryInto;
 
+        let selected_message_id = self.search_matches.get(self.search_selected).map(|m| m.0);
+        let is_first_scan = !self.initialized_rooms.contains(current_room);
+
         self.messages.clear();
+        self.row_heights.clear();
+        self.message_row_index.clear();
+        self.search_matches.clear();
+        self.mention_rows.clear();
         let mut last_date = DateTime::<Utc>::MIN_UTC
             .format(&self.date_format)
             .to_string();
@@ -75,21 +406,17 @@ impl ChatBox<'_> {
         {
             let date_str = message_data.get_date_str(&self.date_format);
             if date_str != last_date {
-                let mut date: Vec<Cell> = vec![
+                let label = if date_str == Local::now().format(&self.date_format).to_string() {
+                    String::from("Today! ") + date_str.as_str()
+                } else {
+                    date_str.clone()
+                };
+                let date = self.row_cells(
                     "".into(),
                     "".into(),
-                    Span::styled(date_str.clone(), self.unread_message_style).into(),
-                ];
-                if date_str == Local::now().format(&self.date_format).to_string() {
-                    let today_str = String::from("Today! ");
-                    date = vec![
-                        "".into(),
-                        "".into(),
-                        Span::styled(today_str + date_str.as_str(), self.unread_message_style)
-                            .into(),
-                    ];
-                }
-                self.messages.push(Row::new(date));
+                    Span::styled(label, self.unread_message_style).into(),
+                );
+                self.push_row(Row::new(date), 1);
                 last_date = date_str;
             }
 
@@ -103,6 +430,33 @@ impl ChatBox<'_> {
                 colour.blue() as u8,
             ));
 
+            let mentions_me = is_mentioned(
+                message_data.get_message(),
+                &self.current_user_id,
+                &self.current_user_name,
+            );
+            let room_has_unread = backend.get_room(current_room).has_unread();
+            let last_read_id = room_has_unread.then(|| backend.get_room(current_room).get_last_read());
+            let is_new_unread = last_read_id.is_some_and(|id| message_data.get_id() > id);
+            if is_first_scan {
+                // baseline this room's pre-existing history without notifying for it
+                self.seen_message_ids.insert(message_data.get_id());
+            } else if self.seen_message_ids.insert(message_data.get_id()) && (mentions_me || is_new_unread) {
+                if let Some(sink) = self.notify_sink.as_mut() {
+                    sink(NotificationEvent {
+                        room_token: current_room.clone(),
+                        actor_display_name: message_data.get_name().to_string(),
+                        message_preview: message_data.get_message().chars().take(80).collect(),
+                        is_mention: mentions_me,
+                    });
+                }
+            }
+            let name_style = if mentions_me {
+                self.mention_style
+            } else {
+                name_style
+            };
+
             let name = textwrap::wrap(
                 message_data.get_name().to_string().as_str(),
                 Options::new(NAME_WIDTH.into()).break_words(true),
@@ -113,50 +467,257 @@ impl ChatBox<'_> {
             .map(|l| l.style(name_style))
             .collect_vec();
 
-            let message_string = message_data
-                .get_message()
-                .split('\n')
-                .flat_map(|cell| {
-                    textwrap::wrap(cell, self.width as usize)
-                        .into_iter()
-                        .map(std::borrow::Cow::into_owned)
-                        .map(Line::from)
-                        .collect_vec()
+            let raw_message = message_data.get_message();
+            let blocks = parse_markdown(
+                raw_message,
+                self.render_markdown,
+                self.bold_style,
+                self.italic_style,
+                self.code_style,
+            );
+
+            // Flatten the blocks into the text that is actually displayed, so
+            // search matches (and their highlight offsets) line up with what
+            // the user sees rather than the raw markdown source.
+            let mut display_text = String::new();
+            let mut line_offsets: Vec<usize> = Vec::new();
+            for block in &blocks {
+                match block {
+                    MdBlock::Paragraph(lines) => {
+                        for line in lines {
+                            line_offsets.push(display_text.len());
+                            display_text.push_str(&line.text);
+                            display_text.push('\n');
+                        }
+                    }
+                    MdBlock::Code(lines) => {
+                        for line in lines {
+                            line_offsets.push(display_text.len());
+                            display_text.push_str(line);
+                            display_text.push('\n');
+                        }
+                    }
+                }
+            }
+
+            let matched_indices = if self.search_query.is_empty() {
+                Vec::new()
+            } else if let Some((score, indices)) = fuzzy_match(&self.search_query, &display_text) {
+                self.search_matches
+                    .push((message_data.get_id(), score, indices.clone()));
+                indices
+            } else {
+                Vec::new()
+            };
+
+            let mut line_offset_iter = line_offsets.into_iter();
+            let message_string = blocks
+                .iter()
+                .flat_map(|block| match block {
+                    MdBlock::Paragraph(lines) => lines
+                        .iter()
+                        .flat_map(|line| {
+                            let cell_offset = line_offset_iter.next().unwrap_or(0);
+                            let mut search_from = 0usize;
+                            textwrap::wrap(&line.text, self.width as usize)
+                                .into_iter()
+                                .map(std::borrow::Cow::into_owned)
+                                .map(|wrapped| {
+                                    let trimmed = wrapped.trim_start();
+                                    let (local_offset, display_offset) =
+                                        find_wrapped_offset(&line.text, trimmed, cell_offset, search_from);
+                                    search_from = local_offset + trimmed.len();
+                                    let local_styles = line
+                                        .styles
+                                        .iter()
+                                        .filter(|(r, _)| {
+                                            r.start < local_offset + wrapped.len()
+                                                && r.end > local_offset
+                                        })
+                                        .map(|(r, s)| {
+                                            (
+                                                r.start.saturating_sub(local_offset)
+                                                    ..r.end.saturating_sub(local_offset),
+                                                *s,
+                                            )
+                                        })
+                                        .collect_vec();
+                                    style_line(
+                                        &wrapped,
+                                        display_offset,
+                                        self.default_style,
+                                        &local_styles,
+                                        &matched_indices,
+                                        self.unread_message_style,
+                                    )
+                                })
+                                .collect_vec()
+                        })
+                        .collect_vec(),
+                    MdBlock::Code(lines) => lines
+                        .iter()
+                        .map(|line| {
+                            let cell_offset = line_offset_iter.next().unwrap_or(0);
+                            let rendered = style_line(
+                                line,
+                                cell_offset,
+                                self.code_block_style,
+                                &[],
+                                &matched_indices,
+                                self.unread_message_style,
+                            );
+                            let mut spans = vec![Span::styled(CODE_BLOCK_INDENT, self.code_block_style)];
+                            spans.extend(rendered.spans);
+                            Line::from(spans)
+                        })
+                        .collect_vec(),
                 })
                 .collect_vec();
 
+            self.message_row_index
+                .insert(message_data.get_id(), self.messages.len());
+            if mentions_me {
+                self.mention_rows.push(self.messages.len());
+            }
+
             let row_height: u16 = if message_string.len() > name.len() {
                 message_string.len().try_into().expect("message too long")
             } else {
                 name.len().try_into().expect("name too long")
             };
-            let message: Vec<Cell> = vec![
-                message_data.get_time_str().into(),
-                name.into(),
-                message_string.into(),
-            ];
+            let time_str = match self.timestamp_style {
+                TimestampStyle::Relative => relative_time_str(message_data.get_timestamp()),
+                TimestampStyle::Absolute | TimestampStyle::Hidden => message_data.get_time_str(),
+            };
+            let message = self.row_cells(time_str.into(), name.into(), message_string.into());
 
-            self.messages.push(Row::new(message).height(row_height));
+            self.push_row(Row::new(message), row_height);
 
-            if message_data.has_reactions() {
-                let reaction: Vec<Cell> = vec![
+            if mentions_me {
+                let mention_marker = self.row_cells(
                     "".into(),
                     "".into(),
-                    message_data.get_reactions_str().into(),
-                ];
-                self.messages.push(Row::new(reaction));
+                    Span::styled(">>> MENTIONED YOU <<<", self.mention_style).into(),
+                );
+                self.push_row(Row::new(mention_marker), 1);
             }
-            if backend.get_room(current_room).has_unread()
-                && backend.get_room(current_room).get_last_read() == message_data.get_id()
-            {
-                let unread_marker: Vec<Cell> = vec![
+
+            if message_data.has_reactions() {
+                let reaction =
+                    self.row_cells("".into(), "".into(), message_data.get_reactions_str().into());
+                self.push_row(Row::new(reaction), 1);
+            }
+            if last_read_id == Some(message_data.get_id()) {
+                let unread_marker = self.row_cells(
                     "".into(),
                     "".into(),
                     Span::styled("+++ LAST READ +++", self.unread_message_style).into(),
-                ];
-                self.messages.push(Row::new(unread_marker));
+                );
+                self.push_row(Row::new(unread_marker), 1);
             }
         }
+
+        self.search_matches.sort_by(|a, b| b.1.cmp(&a.1));
+        self.search_selected = selected_message_id
+            .and_then(|id| self.search_matches.iter().position(|m| m.0 == id))
+            .unwrap_or(0);
+        self.initialized_rooms.insert(current_room.clone());
+    }
+
+    // checks a room the app isn't currently displaying for genuinely new or
+    // mentioning messages and feeds them to notify_sink, without touching any
+    // of the render state used for the room on screen. The app event loop
+    // should call this once per tick for every room other than the focused
+    // one, so notifications keep working while sechat-rs is backgrounded.
+    pub fn check_background_room(&mut self, backend: &impl NCBackend, room: &Token) {
+        let is_first_scan = !self.initialized_rooms.contains(room);
+        let room_has_unread = backend.get_room(room).has_unread();
+        let last_read_id = room_has_unread.then(|| backend.get_room(room).get_last_read());
+
+        for message_data in backend
+            .get_room(room)
+            .get_messages()
+            .values()
+            .filter(|mes| !mes.is_reaction() && !mes.is_edit_note() && !mes.is_comment_deleted())
+        {
+            if is_first_scan {
+                self.seen_message_ids.insert(message_data.get_id());
+                continue;
+            }
+            let mentions_me = is_mentioned(message_data.get_message(), &self.current_user_id, &self.current_user_name);
+            let is_new_unread = last_read_id.is_some_and(|id| message_data.get_id() > id);
+            if self.seen_message_ids.insert(message_data.get_id()) && (mentions_me || is_new_unread) {
+                if let Some(sink) = self.notify_sink.as_mut() {
+                    sink(NotificationEvent {
+                        room_token: room.clone(),
+                        actor_display_name: message_data.get_name().to_string(),
+                        message_preview: message_data.get_message().chars().take(80).collect(),
+                        is_mention: mentions_me,
+                    });
+                }
+            }
+        }
+        self.initialized_rooms.insert(room.clone());
+    }
+
+    pub fn search(&mut self, query: &str, backend: &impl NCBackend, current_room: &Token) {
+        self.search_query = query.to_string();
+        self.update_messages(backend, current_room);
+        if !self.search_matches.is_empty() {
+            self.select_match(0);
+        }
+    }
+
+    pub fn select_next_match(&mut self) {
+        if self.search_matches.is_empty() {
+            return;
+        }
+        let next = (self.search_selected + 1) % self.search_matches.len();
+        self.select_match(next);
+    }
+
+    pub fn select_prev_match(&mut self) {
+        if self.search_matches.is_empty() {
+            return;
+        }
+        let prev = (self.search_selected + self.search_matches.len() - 1) % self.search_matches.len();
+        self.select_match(prev);
+    }
+
+    fn select_match(&mut self, match_index: usize) {
+        self.search_selected = match_index;
+        let message_id = self.search_matches[match_index].0;
+        if let Some(&row_index) = self.message_row_index.get(&message_id) {
+            self.current_index = row_index;
+            self.state.select(Some(self.current_index));
+        }
+    }
+
+    pub fn select_next_mention(&mut self) {
+        let Some(&next) = self
+            .mention_rows
+            .iter()
+            .find(|&&row| row > self.current_index)
+            .or_else(|| self.mention_rows.first())
+        else {
+            return;
+        };
+        self.current_index = next;
+        self.state.select(Some(self.current_index));
+    }
+
+    pub fn select_prev_mention(&mut self) {
+        let Some(&prev) = self
+            .mention_rows
+            .iter()
+            .rev()
+            .find(|&&row| row < self.current_index)
+            .or_else(|| self.mention_rows.last())
+        else {
+            return;
+        };
+        self.current_index = prev;
+        self.state.select(Some(self.current_index));
     }
 
     pub fn select_last_message(&mut self) {
@@ -165,8 +726,10 @@ impl ChatBox<'_> {
         self.state.select(Some(self.current_index));
     }
 
-    pub fn render_area(&self, frame: &mut Frame, area: Rect) {
-        frame.render_stateful_widget(self, area, &mut self.state.clone());
+    pub fn render_area(&mut self, frame: &mut Frame, area: Rect) {
+        let mut state = std::mem::take(&mut self.state);
+        frame.render_stateful_widget(&*self, area, &mut state);
+        self.state = state;
     }
 
     pub fn select_up(&mut self) {
@@ -184,20 +747,32 @@ impl ChatBox<'_> {
             .clamp(0, self.messages.len() - 1);
         self.state.select(Some(self.current_index));
     }
-    pub fn select_line(&mut self, position: Position) -> Result<(), Box<dyn std::error::Error>> {
-        log::debug!(
-            "Got Position {:?} and selected {:?}",
-            position,
-            self.state.selected().ok_or("nothing selected")?
-        );
+    // rows have variable heights, so walk row_heights from the scroll offset
+    // accumulating until they cover the clicked y
+    pub fn select_line(&mut self, position: Position) -> Result<usize, Box<dyn std::error::Error>> {
+        const HEADER_HEIGHT: u16 = 1;
+
+        if self.messages.is_empty() {
+            return Err("no messages to select".into());
+        }
 
-        // let new_selection = state.selected().ok_or("nothing selected")?;
-        // self.current_index = position
-        //     .y
-        //     .clamp(0, (self.messages.len() - 1).try_into()?)
-        //     .try_into()?;
-        // Ok(())
-        todo!("commented code missing?");
+        let offset = self.state.offset();
+        let target_y = position.y.saturating_sub(HEADER_HEIGHT);
+
+        let mut accumulated: u16 = 0;
+        let mut resolved = offset;
+        for (row, &height) in self.row_heights.iter().enumerate().skip(offset) {
+            if accumulated > target_y {
+                break;
+            }
+            resolved = row;
+            accumulated += height.max(1);
+        }
+
+        self.current_index = resolved.clamp(0, self.messages.len() - 1);
+        self.state.select(Some(self.current_index));
+        log::debug!("Got Position {position:?} and selected row {}", self.current_index);
+        Ok(self.current_index)
     }
 }
 
@@ -205,16 +780,21 @@ impl StatefulWidget for &ChatBox<'_> {
     type State = TableState;
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         // Columns widths are constrained in the same way as Layout...
-        let widths = [
-            Constraint::Length(TIME_WIDTH),
-            Constraint::Length(NAME_WIDTH),
-            Constraint::Min(10),
-        ];
+        let widths = if self.timestamp_style == TimestampStyle::Hidden {
+            vec![Constraint::Length(NAME_WIDTH), Constraint::Min(10)]
+        } else {
+            vec![
+                Constraint::Length(TIME_WIDTH),
+                Constraint::Length(NAME_WIDTH),
+                Constraint::Min(10),
+            ]
+        };
+        let header = Row::new(self.row_cells("Time".into(), "Name".into(), "Message".into()));
         StatefulWidget::render(
             Table::new(self.messages.clone(), widths)
                 .column_spacing(1)
                 .style(self.default_style)
-                .header(Row::new(vec!["Time", "Name", "Message"]).style(self.table_header_style))
+                .header(header.style(self.table_header_style))
                 .block(Block::default())
                 .row_highlight_style(self.default_highlight_style)
                 .highlight_spacing(HighlightSpacing::Never),
@@ -276,7 +856,7 @@ mod tests {
 
         let backend = TestBackend::new(40, 10);
         let mut terminal = Terminal::new(backend).unwrap();
-        let mut chat_box = ChatBox::new(&config);
+        let mut chat_box = ChatBox::new(&config, "self_id", "Self");
 
         let mut dummy_user = NCReqDataParticipants::default();
         dummy_user.displayName = "Butz".to_string();
@@ -374,4 +954,177 @@ mod tests {
 
         terminal.backend().assert_buffer(&expected);
     }
+
+    #[test]
+    fn select_line_clicks_into_tall_wrapped_message() {
+        let dir = tempfile::tempdir().unwrap();
+
+        std::env::set_var("HOME", dir.path().as_os_str());
+        let config = init("./test/").unwrap();
+
+        let mut mock_nc_backend = MockNCTalk::new();
+        let mut mock_room = MockNCRoomInterface::new();
+        let timestamp = DateTime::<Utc>::from_timestamp(2000, 0).unwrap();
+        let long_message = "one two three four five six seven eight nine ten".to_string();
+        let mock_message = NCMessage::from(NCReqDataMessage {
+            id: 0,
+            message: long_message.clone(),
+            messageType: "comment".to_string(),
+            actorDisplayName: "Hundi".to_string(),
+            timestamp: timestamp.timestamp(),
+            actorId: "abcd1234".to_string(),
+            ..Default::default()
+        });
+        let message_tree = BTreeMap::from([(1, mock_message)]);
+
+        mock_room
+            .expect_get_messages()
+            .once()
+            .return_const(message_tree);
+        mock_room.expect_has_unread().once().return_const(false);
+        mock_nc_backend
+            .expect_get_room()
+            .times(2)
+            .return_const(mock_room);
+
+        let mut chat_box = ChatBox::new(&config, "self_id", "Self");
+        chat_box.update_messages(&mock_nc_backend, &"123".to_string());
+
+        // One date separator row, then the (wrapped) message row.
+        assert_eq!(chat_box.messages.len(), 2);
+        let wrapped_line_count = textwrap::wrap(&long_message, chat_box.width as usize).len();
+        assert_eq!(chat_box.row_heights, vec![1, wrapped_line_count as u16]);
+        assert!(wrapped_line_count >= 3, "test needs a message that wraps to several lines");
+
+        // Click into a line in the middle of the wrapped message: 1 header
+        // row, 1 date-separator row, then halfway down the message rows.
+        let middle_message_line = (wrapped_line_count / 2) as u16;
+        let click_y = 1 + 1 + middle_message_line;
+        let selected = chat_box
+            .select_line(Position::new(5, click_y))
+            .expect("message list is not empty");
+
+        assert_eq!(selected, 1);
+        assert_eq!(chat_box.current_index, 1);
+        assert_eq!(chat_box.state.selected(), Some(1));
+    }
+
+    #[test]
+    fn fuzzy_match_requires_subsequence_in_order() {
+        assert!(fuzzy_match("btz", "Butz").is_some());
+        assert!(fuzzy_match("ztb", "Butz").is_none());
+        assert!(fuzzy_match("xyz", "Butz").is_none());
+    }
+
+    #[test]
+    fn fuzzy_match_rewards_word_boundary_and_consecutive_runs() {
+        let (boundary_score, _) = fuzzy_match("b", "a boring").unwrap();
+        let (mid_word_score, _) = fuzzy_match("b", "abroad").unwrap();
+        assert!(boundary_score > mid_word_score);
+
+        let (consecutive_score, _) = fuzzy_match("ab", "ab").unwrap();
+        let (gapped_score, _) = fuzzy_match("ab", "a  b").unwrap();
+        assert!(consecutive_score > gapped_score);
+    }
+
+    #[test]
+    fn fuzzy_match_returns_matched_byte_indices() {
+        let (_, indices) = fuzzy_match("at", "cat").unwrap();
+        assert_eq!(indices, vec![1, 2]);
+    }
+
+    #[test]
+    fn parse_inline_strips_markers_and_records_styles() {
+        let bold = Style::new().add_modifier(Modifier::BOLD);
+        let italic = Style::new().add_modifier(Modifier::ITALIC);
+        let code = Style::new().bg(Color::DarkGray);
+
+        let md_line = parse_inline("**bold** *italic* `code` plain", bold, italic, code);
+
+        assert_eq!(md_line.text, "bold italic code plain");
+        assert_eq!(
+            md_line.styles,
+            vec![(0..4, bold), (5..11, italic), (12..16, code)]
+        );
+    }
+
+    #[test]
+    fn parse_markdown_splits_fenced_code_into_its_own_block() {
+        let bold = Style::new().add_modifier(Modifier::BOLD);
+        let italic = Style::new().add_modifier(Modifier::ITALIC);
+        let code = Style::new().bg(Color::DarkGray);
+
+        let blocks = parse_markdown("before\n```\nlet x = 1;\n```\nafter", true, bold, italic, code);
+
+        assert_eq!(blocks.len(), 3);
+        assert!(matches!(&blocks[0], MdBlock::Paragraph(lines) if lines[0].text == "before"));
+        assert!(matches!(&blocks[1], MdBlock::Code(lines) if lines == &vec!["let x = 1;".to_string()]));
+        assert!(matches!(&blocks[2], MdBlock::Paragraph(lines) if lines[0].text == "after"));
+    }
+
+    #[test]
+    fn parse_markdown_plain_mode_is_a_no_op_passthrough() {
+        let bold = Style::new().add_modifier(Modifier::BOLD);
+        let italic = Style::new().add_modifier(Modifier::ITALIC);
+        let code = Style::new().bg(Color::DarkGray);
+
+        let blocks = parse_markdown("**not bold**\nsecond line", false, bold, italic, code);
+
+        let MdBlock::Paragraph(lines) = &blocks[0] else {
+            panic!("expected a single paragraph block");
+        };
+        assert_eq!(lines[0].text, "**not bold**");
+        assert!(lines[0].styles.is_empty());
+        assert_eq!(lines[1].text, "second line");
+    }
+
+    #[test]
+    fn is_mentioned_matches_id_or_name() {
+        assert!(is_mentioned("hey @self_id, look", "self_id", "Self"));
+        assert!(is_mentioned("hey @Self, look", "self_id", "Self"));
+        assert!(!is_mentioned("hey @someone_else, look", "self_id", "Self"));
+    }
+
+    #[test]
+    fn is_mentioned_requires_a_boundary_after_the_name() {
+        // "Al" must not match inside "@Alice".
+        assert!(!is_mentioned("@Alice, can you look at this?", "", "Al"));
+        assert!(is_mentioned("@Al, can you look at this?", "", "Al"));
+        assert!(is_mentioned("@Al!", "", "Al"));
+    }
+
+    #[test]
+    fn mention_navigation_wraps_in_both_directions() {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("HOME", dir.path().as_os_str());
+        let config = init("./test/").unwrap();
+
+        let mut chat_box = ChatBox::new(&config, "self_id", "Self");
+        chat_box.mention_rows = vec![2, 5, 9];
+        chat_box.current_index = 0;
+
+        chat_box.select_next_mention();
+        assert_eq!(chat_box.current_index, 2);
+        chat_box.select_next_mention();
+        assert_eq!(chat_box.current_index, 5);
+        chat_box.select_next_mention();
+        assert_eq!(chat_box.current_index, 9);
+        chat_box.select_next_mention();
+        assert_eq!(chat_box.current_index, 2, "should wrap back to the first mention");
+
+        chat_box.select_prev_mention();
+        assert_eq!(chat_box.current_index, 9, "should wrap back to the last mention");
+    }
+
+    #[test]
+    fn relative_time_str_buckets_by_age() {
+        let now = Utc::now();
+        let ts = |delta: chrono::Duration| (now - delta).timestamp();
+
+        assert_eq!(relative_time_str(ts(chrono::Duration::seconds(10))), "now");
+        assert_eq!(relative_time_str(ts(chrono::Duration::minutes(5))), "5m");
+        assert_eq!(relative_time_str(ts(chrono::Duration::hours(3))), "3h");
+        assert_eq!(relative_time_str(ts(chrono::Duration::days(1))), "yesterday");
+        assert_eq!(relative_time_str(ts(chrono::Duration::days(4))), "4d");
+    }
 }